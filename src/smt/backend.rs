@@ -0,0 +1,504 @@
+//! Runtime solver selection.
+//!
+//! Rather than baking a concrete `SMTBackend` in at compile time, `connect` builds one from a
+//! `SolverConfig` describing which solver binary to run and how to reach it, analogous to how
+//! Isabelle lets a user pick `z3` vs `remote_z3` at invocation time. Because every solver speaks
+//! SMT-LIB2, only the I/O layer (`Transport`) differs; assertion-generation code upstream is
+//! unaffected by the choice.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use smt::smt::{Logic, SMTBackend, SMTError, SMTResult, Type};
+use smt::value::BitVecValue;
+
+/// Which SMT solver binary to drive.
+#[derive(Clone, Debug)]
+pub enum Solver {
+    Z3,
+    CVC4,
+    CVC5,
+    Boolector,
+}
+
+impl Solver {
+    fn binary(&self) -> &'static str {
+        match *self {
+            Solver::Z3 => "z3",
+            Solver::CVC4 => "cvc4",
+            Solver::CVC5 => "cvc5",
+            Solver::Boolector => "boolector",
+        }
+    }
+}
+
+/// Where the solver's SMT-LIB2 stdin/stdout is actually connected.
+#[derive(Clone, Debug)]
+pub enum Transport {
+    /// Spawn the solver as a local child process and talk over its stdin/stdout pipes.
+    Local,
+    /// Relay SMT-LIB2 text to/from a remote solver listening on `host:port`.
+    Remote { endpoint: String },
+}
+
+/// Describes which solver to run, with what flags, and how to reach it.
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub solver: Solver,
+    pub args: Vec<String>,
+    pub transport: Transport,
+}
+
+impl SolverConfig {
+    pub fn new(solver: Solver, transport: Transport) -> SolverConfig {
+        SolverConfig {
+            solver: solver,
+            args: Vec::new(),
+            transport: transport,
+        }
+    }
+}
+
+/// Build the backend described by `config`. If the chosen solver is unavailable, the caller can
+/// retry `connect` with a different `SolverConfig` (another binary, or a remote endpoint) without
+/// touching any assertion-generation code — failure to spawn or connect is reported as an `Err`
+/// rather than panicking the whole process.
+pub fn connect(config: SolverConfig) -> io::Result<Box<dyn SMTBackend<Ident = String, Assertion = String>>> {
+    match config.transport {
+        Transport::Local => {
+            PipedSolver::spawn(&config).map(|b| Box::new(b) as Box<dyn SMTBackend<Ident = String, Assertion = String>>)
+        }
+        Transport::Remote { ref endpoint } => RemoteSolver::connect(endpoint)
+            .map(|b| Box::new(b) as Box<dyn SMTBackend<Ident = String, Assertion = String>>),
+    }
+}
+
+/// A solver reached by spawning it as a local child process and piping SMT-LIB2 text over its
+/// stdin/stdout.
+pub struct PipedSolver {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl PipedSolver {
+    fn spawn(config: &SolverConfig) -> io::Result<PipedSolver> {
+        let mut child = Command::new(config.solver.binary())
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "solver stdin was not piped"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "solver stdout was not piped"))?;
+        Ok(PipedSolver {
+            child: child,
+            stdin: stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+}
+
+/// A solver reached over a plain TCP socket that relays SMT-LIB2 text, for pointing Rune at a
+/// beefier remote machine instead of recompiling against a local-only transport.
+pub struct RemoteSolver {
+    write_half: TcpStream,
+    read_half: BufReader<TcpStream>,
+}
+
+impl RemoteSolver {
+    fn connect(endpoint: &str) -> io::Result<RemoteSolver> {
+        let write_half = TcpStream::connect(endpoint)?;
+        let read_half = write_half.try_clone()?;
+        Ok(RemoteSolver {
+            write_half: write_half,
+            read_half: BufReader::new(read_half),
+        })
+    }
+}
+
+impl SMTBackend for PipedSolver {
+    type Ident = String;
+    type Assertion = String;
+
+    fn set_logic(&mut self, logic: Logic) {
+        set_logic(self, logic);
+    }
+
+    fn new_var(&mut self, ident: Self::Ident, ty: Type) {
+        new_var(self, ident, ty);
+    }
+
+    fn assert(&mut self, ident: Self::Ident, constraint: Self::Assertion) {
+        assert(self, ident, constraint);
+    }
+
+    fn assert_named(&mut self, ident: Self::Ident, constraint: Self::Assertion, label: &str) {
+        assert_named(self, ident, constraint, label);
+    }
+
+    fn push(&mut self, n: usize) {
+        push(self, n);
+    }
+
+    fn pop(&mut self, n: usize) {
+        pop(self, n);
+    }
+
+    fn get_unsat_core(&mut self) -> SMTResult<Vec<Self::Ident>> {
+        get_unsat_core(self)
+    }
+
+    fn declare_fun(&mut self, ident: Self::Ident, args: Vec<Type>, ret: Type) {
+        declare_fun(self, ident, args, ret);
+    }
+
+    fn add_axiom(&mut self, axiom: Self::Assertion) {
+        add_axiom(self, axiom);
+    }
+
+    fn check_sat_async(&mut self) {
+        self.raw_write("(check-sat)\n");
+    }
+
+    fn parse_sat(&mut self) -> SMTResult<bool> {
+        parse_sat(self)
+    }
+
+    fn solve_async(&mut self) {
+        self.raw_write("(check-sat)\n");
+        self.raw_write("(get-model)\n");
+    }
+
+    fn parse_model(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>> {
+        parse_model(self)
+    }
+
+    fn raw_write(&mut self, text: &str) {
+        self.stdin
+            .write_all(text.as_bytes())
+            .expect("failed to write to solver stdin");
+        self.stdin.flush().expect("failed to flush solver stdin");
+    }
+
+    fn raw_read(&mut self) -> String {
+        let mut line = String::new();
+        self.stdout
+            .read_line(&mut line)
+            .expect("failed to read from solver stdout");
+        line
+    }
+}
+
+impl SMTBackend for RemoteSolver {
+    type Ident = String;
+    type Assertion = String;
+
+    fn set_logic(&mut self, logic: Logic) {
+        set_logic(self, logic);
+    }
+
+    fn new_var(&mut self, ident: Self::Ident, ty: Type) {
+        new_var(self, ident, ty);
+    }
+
+    fn assert(&mut self, ident: Self::Ident, constraint: Self::Assertion) {
+        assert(self, ident, constraint);
+    }
+
+    fn assert_named(&mut self, ident: Self::Ident, constraint: Self::Assertion, label: &str) {
+        assert_named(self, ident, constraint, label);
+    }
+
+    fn push(&mut self, n: usize) {
+        push(self, n);
+    }
+
+    fn pop(&mut self, n: usize) {
+        pop(self, n);
+    }
+
+    fn get_unsat_core(&mut self) -> SMTResult<Vec<Self::Ident>> {
+        get_unsat_core(self)
+    }
+
+    fn declare_fun(&mut self, ident: Self::Ident, args: Vec<Type>, ret: Type) {
+        declare_fun(self, ident, args, ret);
+    }
+
+    fn add_axiom(&mut self, axiom: Self::Assertion) {
+        add_axiom(self, axiom);
+    }
+
+    fn check_sat_async(&mut self) {
+        self.raw_write("(check-sat)\n");
+    }
+
+    fn parse_sat(&mut self) -> SMTResult<bool> {
+        parse_sat(self)
+    }
+
+    fn solve_async(&mut self) {
+        self.raw_write("(check-sat)\n");
+        self.raw_write("(get-model)\n");
+    }
+
+    fn parse_model(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>> {
+        parse_model(self)
+    }
+
+    fn raw_write(&mut self, text: &str) {
+        self.write_half
+            .write_all(text.as_bytes())
+            .expect("failed to write to remote solver");
+        self.write_half.flush().expect("failed to flush remote solver connection");
+    }
+
+    fn raw_read(&mut self) -> String {
+        let mut line = String::new();
+        self.read_half
+            .read_line(&mut line)
+            .expect("failed to read from remote solver");
+        line
+    }
+}
+
+// The SMT-LIB2 text generated for each command is identical regardless of transport, so it is
+// shared here rather than duplicated between `PipedSolver` and `RemoteSolver`.
+
+fn set_logic<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, logic: Logic) {
+    backend.raw_write(&format!("(set-logic {})\n", logic));
+    backend.raw_write("(set-option :produce-unsat-cores true)\n");
+    backend.raw_write("(set-option :produce-models true)\n");
+}
+
+fn new_var<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, ident: String, ty: Type) {
+    backend.raw_write(&format!("(declare-const {} {})\n", ident, ty));
+}
+
+fn assert<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, _ident: String, constraint: String) {
+    backend.raw_write(&format!("(assert {})\n", constraint));
+}
+
+fn assert_named<B: SMTBackend<Ident = String, Assertion = String>>(
+    backend: &mut B,
+    _ident: String,
+    constraint: String,
+    label: &str,
+) {
+    backend.raw_write(&format!("(assert (! {} :named {}))\n", constraint, label));
+}
+
+fn push<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, n: usize) {
+    backend.raw_write(&format!("(push {})\n", n));
+}
+
+fn pop<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, n: usize) {
+    backend.raw_write(&format!("(pop {})\n", n));
+}
+
+fn get_unsat_core<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B) -> SMTResult<Vec<String>> {
+    backend.raw_write("(get-unsat-core)\n");
+    Ok(backend
+        .raw_read()
+        .trim_matches(|c| c == '(' || c == ')' || c == '\n')
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect())
+}
+
+fn declare_fun<B: SMTBackend<Ident = String, Assertion = String>>(
+    backend: &mut B,
+    ident: String,
+    args: Vec<Type>,
+    ret: Type,
+) {
+    let argsorts = args.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ");
+    backend.raw_write(&format!("(declare-fun {} ({}) {})\n", ident, argsorts, ret));
+}
+
+fn add_axiom<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B, axiom: String) {
+    backend.raw_write(&format!("(assert {})\n", axiom));
+}
+
+fn parse_sat<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B) -> SMTResult<bool> {
+    match backend.raw_read().trim() {
+        "sat" => Ok(true),
+        "unsat" => Err(SMTError::Unsat),
+        _ => Err(SMTError::Undefined),
+    }
+}
+
+/// Read lines from `backend` until parentheses balance back out to zero, i.e. one full
+/// s-expression (the `(model ...)` response to `get-model` typically spans several lines).
+fn read_sexpr<B: SMTBackend<Ident = String, Assertion = String>>(backend: &mut B) -> String {
+    let mut depth = 0i32;
+    let mut started = false;
+    let mut out = String::new();
+    loop {
+        let line = backend.raw_read();
+        if line.is_empty() {
+            break;
+        }
+        for c in line.chars() {
+            match c {
+                '(' => {
+                    depth += 1;
+                    started = true;
+                }
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        out.push_str(&line);
+        if started && depth <= 0 {
+            break;
+        }
+    }
+    out
+}
+
+/// Parse a `(model (define-fun name () sort value) ...)` response into `name -> value` pairs,
+/// using `BitVecValue::parse` on whichever literal form (`#xNN`, `#bNN`, `(_ bvN w)`) the solver
+/// used for each definition.
+///
+/// `solve_async` queues `(check-sat)` before `(get-model)`, so the first line back is the
+/// check-sat status, exactly like `parse_sat` reads. On `unsat` (or anything but `sat`), the
+/// solver's `get-model` reply is an error s-expression with no `define-fun` in it at all — read
+/// and discard that status line first, the same way `parse_sat` does, rather than falling through
+/// to `read_sexpr` and silently parsing zero models out of it.
+fn parse_model<B: SMTBackend<Ident = String, Assertion = String>>(
+    backend: &mut B,
+) -> SMTResult<HashMap<String, BitVecValue>> {
+    match backend.raw_read().trim() {
+        "sat" => {}
+        "unsat" => return Err(SMTError::Unsat),
+        _ => return Err(SMTError::Undefined),
+    }
+    let sexpr = read_sexpr(backend);
+    let mut model = HashMap::new();
+    for def in sexpr.split("(define-fun").skip(1) {
+        let name = match def.split_whitespace().next() {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let literal_start = def.find("#x").or_else(|| def.find("#b")).or_else(|| def.find("(_ bv"));
+        let literal = match literal_start {
+            Some(start) if def[start..].starts_with("(_ bv") => {
+                let rest = &def[start..];
+                match rest.find(')') {
+                    Some(end) => &rest[..=end],
+                    None => continue,
+                }
+            }
+            Some(start) => {
+                let rest = &def[start..];
+                let end = rest.find(|c: char| c.is_whitespace() || c == ')').unwrap_or(rest.len());
+                &rest[..end]
+            }
+            None => continue,
+        };
+        if let Some(value) = BitVecValue::parse(literal) {
+            model.insert(name, value);
+        }
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Stands in for `PipedSolver`/`RemoteSolver`, replaying canned solver output instead of
+    /// talking to a real process or socket, so `parse_sat`/`parse_model`/`read_sexpr` can be
+    /// exercised without a solver binary on hand.
+    struct MockSolver {
+        replies: VecDeque<String>,
+    }
+
+    impl MockSolver {
+        fn new(replies: &[&str]) -> MockSolver {
+            MockSolver {
+                replies: replies.iter().map(|s| s.to_string()).collect(),
+            }
+        }
+    }
+
+    impl SMTBackend for MockSolver {
+        type Ident = String;
+        type Assertion = String;
+
+        fn set_logic(&mut self, _logic: Logic) {}
+        fn new_var(&mut self, _ident: Self::Ident, _ty: Type) {}
+        fn assert(&mut self, _ident: Self::Ident, _constraint: Self::Assertion) {}
+        fn assert_named(&mut self, _ident: Self::Ident, _constraint: Self::Assertion, _label: &str) {}
+        fn push(&mut self, _n: usize) {}
+        fn pop(&mut self, _n: usize) {}
+        fn get_unsat_core(&mut self) -> SMTResult<Vec<Self::Ident>> {
+            Ok(Vec::new())
+        }
+        fn declare_fun(&mut self, _ident: Self::Ident, _args: Vec<Type>, _ret: Type) {}
+        fn add_axiom(&mut self, _axiom: Self::Assertion) {}
+        fn check_sat_async(&mut self) {}
+        fn parse_sat(&mut self) -> SMTResult<bool> {
+            parse_sat(self)
+        }
+        fn solve_async(&mut self) {}
+        fn parse_model(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>> {
+            parse_model(self)
+        }
+        fn raw_write(&mut self, _text: &str) {}
+        fn raw_read(&mut self) -> String {
+            self.replies.pop_front().unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn parse_model_reports_unsat_instead_of_an_empty_model() {
+        let mut mock = MockSolver::new(&["unsat\n"]);
+        match mock.solve() {
+            Err(SMTError::Unsat) => {}
+            other => panic!("expected Err(SMTError::Unsat), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_model_reports_undefined_on_unknown() {
+        let mut mock = MockSolver::new(&["unknown\n"]);
+        match mock.solve() {
+            Err(SMTError::Undefined) => {}
+            other => panic!("expected Err(SMTError::Undefined), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_model_parses_a_sat_model() {
+        let mut mock = MockSolver::new(&[
+            "sat\n",
+            "(model\n",
+            "  (define-fun x () (_ BitVec 8) #x05)\n",
+            "  (define-fun y () (_ BitVec 4) #b0011)\n",
+            ")\n",
+        ]);
+        let model = mock.solve().expect("sat response should parse to a model");
+        assert_eq!(model.get("x").unwrap().to_u64_lossy(), 5);
+        assert_eq!(model.get("y").unwrap().to_u64_lossy(), 3);
+    }
+
+    #[test]
+    fn read_sexpr_stops_once_parens_balance() {
+        let mut mock = MockSolver::new(&["(model\n", "  (define-fun x () (_ BitVec 8) #x05)\n", ")\n"]);
+        let sexpr = read_sexpr(&mut mock);
+        assert!(sexpr.trim_end().ends_with(")"));
+        assert_eq!(mock.replies.len(), 0);
+    }
+}
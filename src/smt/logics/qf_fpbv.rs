@@ -0,0 +1,24 @@
+use smt::theories::{bitvec, core, fp};
+use smt::smt::{Logic, SMTNode};
+use std::fmt::{Display, Debug};
+use std::fmt;
+
+define_sorts_for_logic!(QF_FPBV_Sorts,
+                        BV -> bitvec::Sorts,
+                        Core -> core::Sorts,
+                        FP -> fp::Sorts
+                        );
+
+define_fns_for_logic!(QF_FPBV_Fn,
+                      BVOps -> bitvec::OpCodes,
+                      CoreOps -> core::OpCodes,
+                      FPOps -> fp::OpCodes
+                      );
+
+define_logic!(QF_FPBV,
+              QF_FPBV_Fn,
+              QF_FPBV_Sorts,
+              map { QF_FPBV_Sorts::BV(_) => bitvec::OpCodes::FreeVar,
+                  QF_FPBV_Sorts::FP(_) => fp::OpCodes::FreeVar
+              }
+              );
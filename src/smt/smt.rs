@@ -5,6 +5,8 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt;
 
+use smt::value::BitVecValue;
+
 #[derive(Clone, Copy, Debug)]
 pub enum SMTError {
     Undefined,
@@ -18,6 +20,13 @@ pub enum Logic {
     QF_AX,
     QF_ABV,
     QF_AUFB,
+    /// Uninterpreted functions, used standalone or composed into another logic (e.g.
+    /// `QF_AUFBV`, which already names "UF" in its acronym) to model opaque functions that are
+    /// only constrained by their declared signature and any axioms asserted over them.
+    UF,
+    /// Quantifier-free bitvectors composed with IEEE 754 floating-point, as used by
+    /// `smt::logics::qf_fpbv`.
+    QF_FPBV,
 }
 
 impl fmt::Display for Logic {
@@ -27,6 +36,8 @@ impl fmt::Display for Logic {
             Logic::QF_AX => "QF_AX",
             Logic::QF_ABV => "QF_ABV",
             Logic::QF_AUFB => "QF_AUFB",
+            Logic::UF => "UF",
+            Logic::QF_FPBV => "QF_FPBV",
         };
         write!(f, "{}", s)
     }
@@ -36,6 +47,9 @@ impl fmt::Display for Logic {
 pub enum Type {
     Int,
     BitVector(u64),
+    /// IEEE 754 floating-point of the given exponent and significand width, e.g.
+    /// `Float(8, 24)` for `Float32`.
+    Float(u64, u64),
 }
 
 impl fmt::Display for Type {
@@ -43,6 +57,7 @@ impl fmt::Display for Type {
         let s = match *self {
             Type::Int => "Int".to_owned(),
             Type::BitVector(n) => format!("(_ BitVec {})", n),
+            Type::Float(ebits, sbits) => format!("(_ FloatingPoint {} {})", ebits, sbits),
         };
         write!(f, "{}", s)
     }
@@ -56,10 +71,12 @@ pub trait SMT {
     /// context.
     type Idx: Clone + Debug;
 
-    /// Return one solution
-    fn solve_for<B: SMTBackend>(&Self::Idx, &mut B) -> SMTResult<u64>;
-    /// Repeatedly query the SMT solver to obtain all possible solutions for a set of constraints.
-    fn solve_all_for<B: SMTBackend>(&Self::Idx, &mut B) -> SMTResult<Vec<u64>>;
+    /// Return one solution, at the full width the variable was declared with — no truncation to
+    /// `u64`, so this is correct for 128-bit and wider registers.
+    fn solve_for<B: SMTBackend>(&Self::Idx, &mut B) -> SMTResult<BitVecValue>;
+    /// Repeatedly query the SMT solver to obtain all possible solutions for a set of constraints,
+    /// blocking each previous full-width solution in turn (`(assert (not (= var prev_value)))`).
+    fn solve_all_for<B: SMTBackend>(&Self::Idx, &mut B) -> SMTResult<Vec<BitVecValue>>;
     /// Check if the constraints are satisfiable.
     fn check_sat<B: SMTBackend>(&mut self, &mut B) -> SMTResult<bool>;
 }
@@ -74,12 +91,127 @@ pub trait SMTBackend {
     type Ident: Debug + Clone;
     type Assertion: Debug + Clone;
 
+    /// Set the logic to use. Implementations should also emit
+    /// `(set-option :produce-unsat-cores true)` and `(set-option :produce-models true)` here so
+    /// that `get_unsat_core` and `solve` can be relied on for the rest of the session.
     fn set_logic(&mut self, Logic);
     fn new_var(&mut self, Self::Ident, Type);
     fn assert(&mut self, Self::Ident, Self::Assertion);
-    fn check_sat(&mut self) -> bool;
-    fn solve(&mut self) -> SMTResult<HashMap<Self::Ident, u64>>;
+    /// Assert `constraint`, tracking it under `label` (via SMT-LIB `:named`) so that it can
+    /// show up in a later `get_unsat_core` response.
+    fn assert_named(&mut self, ident: Self::Ident, constraint: Self::Assertion, label: &str);
+
+    /// Push `n` new assertion scopes onto the solver's stack (`(push n)`).
+    fn push(&mut self, n: usize);
+    /// Pop `n` assertion scopes off the solver's stack (`(pop n)`), discarding any assertions
+    /// made since the matching `push`.
+    fn pop(&mut self, n: usize);
+    /// After an `Unsat` result, fetch the minimal set of named assertions responsible for the
+    /// conflict (`(get-unsat-core)`), parsed back into the `Self::Ident` labels they were
+    /// asserted under.
+    fn get_unsat_core(&mut self) -> SMTResult<Vec<Self::Ident>>;
+
+    /// Declare an uninterpreted function (`(declare-fun name (argsorts) retsort)`). The
+    /// function is only constrained by its signature and whatever axioms are later asserted
+    /// over it via `add_axiom`.
+    fn declare_fun(&mut self, ident: Self::Ident, args: Vec<Type>, ret: Type);
+    /// Assert a standalone constraint that is not tied to a single program variable, such as an
+    /// axiom over an uninterpreted function declared with `declare_fun`
+    /// (e.g. `forall x. f(x) = f(x)`-style consistency constraints).
+    fn add_axiom(&mut self, axiom: Self::Assertion);
+
+    /// Push a `(check-sat)` command to the solver's stdin and return immediately, without
+    /// waiting for or parsing a response.
+    fn check_sat_async(&mut self);
+    /// Block on, and parse, the response to a previously issued `check_sat_async`.
+    fn parse_sat(&mut self) -> SMTResult<bool>;
+    /// Synchronous `check-sat`: fires the async query and parses the result right away.
+    fn check_sat(&mut self) -> bool {
+        self.check_sat_async();
+        self.parse_sat().unwrap_or(false)
+    }
 
-    fn raw_write<T: AsRef<str>>(&mut self, T);
+    /// Push the commands needed to obtain a model (`check-sat` followed by `get-model`) and
+    /// return immediately, without waiting for or parsing a response.
+    fn solve_async(&mut self);
+    /// Block on, and parse, the model produced by a previously issued `solve_async`. Values are
+    /// kept at their declared bitvector width rather than truncated to `u64`.
+    fn parse_model(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>>;
+    /// Synchronous `solve`: fires the async query and parses the model right away.
+    fn solve(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>> {
+        self.solve_async();
+        self.parse_model()
+    }
+
+    fn raw_write(&mut self, text: &str);
     fn raw_read(&mut self) -> String;
 }
+
+/// Forwards to the boxed value, so the trait object `smt::backend::connect` returns
+/// (`Box<dyn SMTBackend<..>>`) can be passed anywhere a `B: SMTBackend` bound is expected — e.g.
+/// straight into `SMT::solve_for` — without the caller needing to know or match on the concrete
+/// `PipedSolver`/`RemoteSolver` underneath.
+impl<T: SMTBackend + ?Sized> SMTBackend for Box<T> {
+    type Ident = T::Ident;
+    type Assertion = T::Assertion;
+
+    fn set_logic(&mut self, logic: Logic) {
+        (**self).set_logic(logic)
+    }
+
+    fn new_var(&mut self, ident: Self::Ident, ty: Type) {
+        (**self).new_var(ident, ty)
+    }
+
+    fn assert(&mut self, ident: Self::Ident, constraint: Self::Assertion) {
+        (**self).assert(ident, constraint)
+    }
+
+    fn assert_named(&mut self, ident: Self::Ident, constraint: Self::Assertion, label: &str) {
+        (**self).assert_named(ident, constraint, label)
+    }
+
+    fn push(&mut self, n: usize) {
+        (**self).push(n)
+    }
+
+    fn pop(&mut self, n: usize) {
+        (**self).pop(n)
+    }
+
+    fn get_unsat_core(&mut self) -> SMTResult<Vec<Self::Ident>> {
+        (**self).get_unsat_core()
+    }
+
+    fn declare_fun(&mut self, ident: Self::Ident, args: Vec<Type>, ret: Type) {
+        (**self).declare_fun(ident, args, ret)
+    }
+
+    fn add_axiom(&mut self, axiom: Self::Assertion) {
+        (**self).add_axiom(axiom)
+    }
+
+    fn check_sat_async(&mut self) {
+        (**self).check_sat_async()
+    }
+
+    fn parse_sat(&mut self) -> SMTResult<bool> {
+        (**self).parse_sat()
+    }
+
+    fn solve_async(&mut self) {
+        (**self).solve_async()
+    }
+
+    fn parse_model(&mut self) -> SMTResult<HashMap<Self::Ident, BitVecValue>> {
+        (**self).parse_model()
+    }
+
+    fn raw_write(&mut self, text: &str) {
+        (**self).raw_write(text)
+    }
+
+    fn raw_read(&mut self) -> String {
+        (**self).raw_read()
+    }
+}
@@ -0,0 +1,249 @@
+//! Theory of fixed-size bitvectors (SMT-LIB `FixedSizeBitVectors` theory), the workhorse theory
+//! for modeling machine words and composed into most of Rune's logics (`QF_BV`, `QF_ABV`,
+//! `QF_AUFBV`, `QF_FPBV`, ...).
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Sorts {
+    BitVec(u64),
+}
+
+impl fmt::Display for Sorts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Sorts::BitVec(n) => write!(f, "(_ BitVec {})", n),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum OpCodes {
+    FreeVar,
+    Const(u64, u64),
+
+    // Bitwise.
+    BvAnd,
+    BvOr,
+    BvXor,
+    BvNot,
+
+    // Arithmetic.
+    BvNeg,
+    BvAdd,
+    BvSub,
+    BvMul,
+    BvUDiv,
+    BvURem,
+    BvSDiv,
+    BvSRem,
+    BvSMod,
+
+    // Shifts.
+    BvShl,
+    BvLShr,
+    BvAShr,
+
+    // Structural.
+    Concat,
+    Extract(u64, u64),
+    /// `(_ repeat n)`: concatenate `n` copies of the operand.
+    Repeat(u64),
+    /// `(_ zero_extend n)`: widen by `n` zero bits.
+    ZeroExtend(u64),
+    /// `(_ sign_extend n)`: widen by `n` copies of the sign bit.
+    SignExtend(u64),
+    /// `(_ rotate_left n)`.
+    RotateLeft(u64),
+    /// `(_ rotate_right n)`.
+    RotateRight(u64),
+
+    // Comparisons.
+    BvULt,
+    BvULe,
+    BvUGt,
+    BvUGe,
+    BvSLt,
+    BvSLe,
+    BvSGt,
+    BvSGe,
+
+    // Overflow-detection predicates, so lifted instruction semantics (flag computation,
+    // saturating ops) can name them as op-codes. They have no `Display` form (printing one
+    // panics); `OpCodes::overflow_formula` expands them into SMT-LIB2 formulas instead.
+    BvSMulDoesNotOverflow,
+    BvSMulDoesNotUnderflow,
+    BvUMulDoesNotOverflow,
+}
+
+impl OpCodes {
+    /// Number of bitvector operands the op-code takes, not counting any parameters baked into
+    /// the op-code itself (e.g. `Extract`'s `hi`/`lo` are parameters, not operands).
+    pub fn arity(&self) -> usize {
+        match *self {
+            OpCodes::FreeVar | OpCodes::Const(..) => 0,
+            OpCodes::BvNot
+            | OpCodes::BvNeg
+            | OpCodes::Extract(..)
+            | OpCodes::Repeat(_)
+            | OpCodes::ZeroExtend(_)
+            | OpCodes::SignExtend(_)
+            | OpCodes::RotateLeft(_)
+            | OpCodes::RotateRight(_) => 1,
+            _ => 2,
+        }
+    }
+
+    /// Check that any width parameters baked into the op-code are sane, independent of whatever
+    /// operand it is applied to (e.g. `Extract`'s `hi` must not be below its `lo`).
+    pub fn validate_widths(&self) -> Result<(), String> {
+        match *self {
+            OpCodes::Extract(hi, lo) if hi < lo => Err(format!(
+                "Extract({}, {}): high index must be >= low index",
+                hi, lo
+            )),
+            OpCodes::Repeat(0) => Err("Repeat(0): must repeat the operand at least once".to_owned()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for OpCodes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            OpCodes::FreeVar => "".to_owned(),
+            OpCodes::Const(value, width) => format!("(_ bv{} {})", value, width),
+            OpCodes::BvAnd => "bvand".to_owned(),
+            OpCodes::BvOr => "bvor".to_owned(),
+            OpCodes::BvXor => "bvxor".to_owned(),
+            OpCodes::BvNot => "bvnot".to_owned(),
+            OpCodes::BvNeg => "bvneg".to_owned(),
+            OpCodes::BvAdd => "bvadd".to_owned(),
+            OpCodes::BvSub => "bvsub".to_owned(),
+            OpCodes::BvMul => "bvmul".to_owned(),
+            OpCodes::BvUDiv => "bvudiv".to_owned(),
+            OpCodes::BvURem => "bvurem".to_owned(),
+            OpCodes::BvSDiv => "bvsdiv".to_owned(),
+            OpCodes::BvSRem => "bvsrem".to_owned(),
+            OpCodes::BvSMod => "bvsmod".to_owned(),
+            OpCodes::BvShl => "bvshl".to_owned(),
+            OpCodes::BvLShr => "bvlshr".to_owned(),
+            OpCodes::BvAShr => "bvashr".to_owned(),
+            OpCodes::Concat => "concat".to_owned(),
+            OpCodes::Extract(hi, lo) => format!("(_ extract {} {})", hi, lo),
+            OpCodes::Repeat(n) => format!("(_ repeat {})", n),
+            OpCodes::ZeroExtend(n) => format!("(_ zero_extend {})", n),
+            OpCodes::SignExtend(n) => format!("(_ sign_extend {})", n),
+            OpCodes::RotateLeft(n) => format!("(_ rotate_left {})", n),
+            OpCodes::RotateRight(n) => format!("(_ rotate_right {})", n),
+            OpCodes::BvULt => "bvult".to_owned(),
+            OpCodes::BvULe => "bvule".to_owned(),
+            OpCodes::BvUGt => "bvugt".to_owned(),
+            OpCodes::BvUGe => "bvuge".to_owned(),
+            OpCodes::BvSLt => "bvslt".to_owned(),
+            OpCodes::BvSLe => "bvsle".to_owned(),
+            OpCodes::BvSGt => "bvsgt".to_owned(),
+            OpCodes::BvSGe => "bvsge".to_owned(),
+            OpCodes::BvSMulDoesNotOverflow
+            | OpCodes::BvSMulDoesNotUnderflow
+            | OpCodes::BvUMulDoesNotOverflow => unreachable!(
+                "overflow-detection predicates have no SMT-LIB2 function symbol any solver \
+                 accepts; use OpCodes::overflow_formula to build the actual formula"
+            ),
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl OpCodes {
+    /// Expand an overflow-detection predicate into a real SMT-LIB2 formula over `lhs`/`rhs`
+    /// (each a `width`-bit bitvector), since unlike every other op-code above these have no
+    /// plain function symbol any mainstream solver's SMT-LIB2 front end accepts. Returns `None`
+    /// for every other op-code.
+    ///
+    /// `BvUMulDoesNotOverflow` uses the round-trip-division check compilers use for software
+    /// overflow checks (redo the inverse operation and compare): `y == 0 || (x * y) / y == x`.
+    /// Unsigned multiplication has no "underflow" direction, so one check is the whole story.
+    ///
+    /// Signed multiplication can wrap in either direction, and the two predicates are meant to
+    /// be independently meaningful (width 4, `x = -8`, `y = 2`: the true product -16 underflows
+    /// past the representable minimum -8 but does not exceed the representable maximum 7), so a
+    /// single round-trip check can't serve both. Instead, sign-extend `lhs`/`rhs` to `2 * width`
+    /// bits, compute the true (non-wrapping) product there, and compare it directly against the
+    /// `width`-bit signed range's max (`BvSMulDoesNotOverflow`) or min (`BvSMulDoesNotUnderflow`).
+    pub fn overflow_formula(&self, width: u64, lhs: &str, rhs: &str) -> Option<String> {
+        match *self {
+            OpCodes::BvUMulDoesNotOverflow => {
+                let zero = format!("(_ bv0 {})", width);
+                Some(format!(
+                    "(or (= {rhs} {zero}) (= (bvudiv (bvmul {lhs} {rhs}) {rhs}) {lhs}))",
+                    lhs = lhs,
+                    rhs = rhs,
+                    zero = zero
+                ))
+            }
+            OpCodes::BvSMulDoesNotOverflow | OpCodes::BvSMulDoesNotUnderflow => {
+                let sext_lhs = format!("((_ sign_extend {}) {})", width, lhs);
+                let sext_rhs = format!("((_ sign_extend {}) {})", width, rhs);
+                let wide_product = format!("(bvmul {} {})", sext_lhs, sext_rhs);
+                // `width`-bit signed max (0111...1) and min (1000...0), built from shifts rather
+                // than a decimal literal of the magnitude itself, so this stays correct past the
+                // point `2^(width - 1)` would overflow a `u64` literal for very wide bitvectors.
+                let max_n = format!("(bvlshr (bvnot (_ bv0 {w})) (_ bv1 {w}))", w = width);
+                let min_n = format!(
+                    "(bvshl (_ bv1 {w}) (_ bv{shift} {w}))",
+                    w = width,
+                    shift = width - 1
+                );
+                let wide_max = format!("((_ sign_extend {}) {})", width, max_n);
+                let wide_min = format!("((_ sign_extend {}) {})", width, min_n);
+                Some(match *self {
+                    OpCodes::BvSMulDoesNotOverflow => format!("(bvsle {} {})", wide_product, wide_max),
+                    _ => format!("(bvsge {} {})", wide_product, wide_min),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arity_matches_operand_count() {
+        assert_eq!(OpCodes::FreeVar.arity(), 0);
+        assert_eq!(OpCodes::BvNot.arity(), 1);
+        assert_eq!(OpCodes::BvAdd.arity(), 2);
+        assert_eq!(OpCodes::BvSMulDoesNotOverflow.arity(), 2);
+    }
+
+    #[test]
+    fn validate_widths_rejects_backwards_extract() {
+        assert!(OpCodes::Extract(2, 5).validate_widths().is_err());
+        assert!(OpCodes::Extract(5, 2).validate_widths().is_ok());
+    }
+
+    #[test]
+    fn non_overflow_opcodes_have_no_overflow_formula() {
+        assert_eq!(OpCodes::BvAdd.overflow_formula(8, "x", "y"), None);
+    }
+
+    #[test]
+    fn overflow_and_underflow_formulas_are_distinct() {
+        // Regression for a bug where both predicates expanded to the exact same round-trip
+        // division check and so could never disagree.
+        let overflow = OpCodes::BvSMulDoesNotOverflow.overflow_formula(4, "x", "y").unwrap();
+        let underflow = OpCodes::BvSMulDoesNotUnderflow.overflow_formula(4, "x", "y").unwrap();
+        assert_ne!(overflow, underflow);
+    }
+
+    #[test]
+    fn overflow_formula_uses_sign_extend_and_width_derived_bounds() {
+        let formula = OpCodes::BvSMulDoesNotOverflow.overflow_formula(4, "x", "y").unwrap();
+        assert!(formula.contains("(_ sign_extend 4)"));
+        assert!(formula.contains("(bvlshr (bvnot (_ bv0 4)) (_ bv1 4))"));
+        assert!(!formula.contains("bvudiv"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Theory of IEEE 754 floating-point arithmetic (SMT-LIB `FloatingPoint` theory), composed into
+//! the `QF_FPBV` logic alongside `bitvec` and `core`.
+
+use std::fmt;
+
+/// Rounding mode argument required by the non-exact arithmetic op-codes, modeled after the
+/// SMT-LIB `RoundingMode` sort.
+#[derive(Clone, Copy, Debug)]
+pub enum RoundingMode {
+    RoundNearestTiesToEven,
+    RoundTowardPositive,
+    RoundTowardNegative,
+    RoundTowardZero,
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            RoundingMode::RoundNearestTiesToEven => "RNE",
+            RoundingMode::RoundTowardPositive => "RTP",
+            RoundingMode::RoundTowardNegative => "RTN",
+            RoundingMode::RoundTowardZero => "RTZ",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Sorts {
+    Float32,
+    Float64,
+    /// `(_ FloatingPoint ebits sbits)` for any other exponent/significand width.
+    Float(u64, u64),
+}
+
+impl fmt::Display for Sorts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Sorts::Float32 => "Float32".to_owned(),
+            Sorts::Float64 => "Float64".to_owned(),
+            Sorts::Float(ebits, sbits) => format!("(_ FloatingPoint {} {})", ebits, sbits),
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum OpCodes {
+    FreeVar,
+
+    FpAdd(RoundingMode),
+    FpSub(RoundingMode),
+    FpMul(RoundingMode),
+    FpDiv(RoundingMode),
+    FpSqrt(RoundingMode),
+    FpFma(RoundingMode),
+
+    FpLt,
+    FpLeq,
+    FpEq,
+
+    FpIsNaN,
+    FpIsInfinite,
+    FpIsZero,
+
+    /// Convert from a (signed) bitvector operand to a float of the given exponent and
+    /// significand width, rounding per the first argument.
+    ToFp(RoundingMode, u64, u64),
+    /// Convert to a (signed) bitvector of the given width, rounding per the first argument.
+    FromFp(RoundingMode, u64),
+}
+
+impl fmt::Display for OpCodes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            OpCodes::FreeVar => "".to_owned(),
+            OpCodes::FpAdd(rm) => format!("fp.add {}", rm),
+            OpCodes::FpSub(rm) => format!("fp.sub {}", rm),
+            OpCodes::FpMul(rm) => format!("fp.mul {}", rm),
+            OpCodes::FpDiv(rm) => format!("fp.div {}", rm),
+            OpCodes::FpSqrt(rm) => format!("fp.sqrt {}", rm),
+            OpCodes::FpFma(rm) => format!("fp.fma {}", rm),
+            OpCodes::FpLt => "fp.lt".to_owned(),
+            OpCodes::FpLeq => "fp.leq".to_owned(),
+            OpCodes::FpEq => "fp.eq".to_owned(),
+            OpCodes::FpIsNaN => "fp.isNaN".to_owned(),
+            OpCodes::FpIsInfinite => "fp.isInfinite".to_owned(),
+            OpCodes::FpIsZero => "fp.isZero".to_owned(),
+            OpCodes::ToFp(rm, ebits, sbits) => format!("(_ to_fp {} {}) {}", ebits, sbits, rm),
+            OpCodes::FromFp(rm, width) => format!("(_ fp.to_sbv {}) {}", width, rm),
+        };
+        write!(f, "{}", s)
+    }
+}
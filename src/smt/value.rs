@@ -0,0 +1,192 @@
+//! Arbitrary-width bitvector values, as returned by `SMTBackend::solve` and friends.
+//!
+//! Solvers report `(_ BitVec n)` values for whatever width `n` was declared; a plain `u64`
+//! silently truncates any `n > 64`, which real binaries hit constantly (SIMD/128-bit registers,
+//! memory-address-width values). `BitVecValue` keeps the full-width bytes instead.
+
+use std::fmt;
+
+/// The value of a bitvector variable, together with its declared width. Bytes are stored
+/// little-endian, padded with zero bytes up to `ceil(width / 8)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitVecValue {
+    width: u64,
+    bytes: Vec<u8>,
+}
+
+impl BitVecValue {
+    pub fn new(width: u64, bytes: Vec<u8>) -> BitVecValue {
+        BitVecValue {
+            width: width,
+            bytes: bytes,
+        }
+    }
+
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    /// Little-endian bytes, `ceil(width / 8)` of them.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Lossily narrow to a `u64`, keeping only the low 64 bits. For callers that know their
+    /// particular value fits (e.g. a flag bit), not for general use on wide values.
+    pub fn to_u64_lossy(&self) -> u64 {
+        let mut out = 0u64;
+        for (i, byte) in self.bytes.iter().take(8).enumerate() {
+            out |= (*byte as u64) << (i * 8);
+        }
+        out
+    }
+
+    /// Parse one of the SMT-LIB model literal forms a solver may hand back for a bitvector
+    /// value: `#xNN` (hex), `#bNN` (binary), or `(_ bvN w)` (decimal value with explicit width).
+    pub fn parse(literal: &str) -> Option<BitVecValue> {
+        let literal = literal.trim();
+        if literal.starts_with("#x") {
+            let digits = &literal[2..];
+            let width = digits.len() as u64 * 4;
+            Some(BitVecValue::new(width, hex_to_le_bytes(digits)))
+        } else if literal.starts_with("#b") {
+            let digits = &literal[2..];
+            let width = digits.len() as u64;
+            Some(BitVecValue::new(width, bin_to_le_bytes(digits)))
+        } else if literal.starts_with("(_ bv") {
+            let rest = &literal[5..literal.len() - 1];
+            let mut parts = rest.split_whitespace();
+            let value = parts.next()?;
+            let width: u64 = parts.next()?.parse().ok()?;
+            Some(BitVecValue::new(width, decimal_to_le_bytes(value, width)))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for BitVecValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Printed as `#b`-binary, one bit per `self.width`, rather than `#x`-hex rounded up to
+        // a whole byte: a non-byte-aligned width (a 1-bit flag, a 12-bit field) would otherwise
+        // print as a literal of the wrong SMT-LIB sort and get rejected when re-asserted, e.g.
+        // in `solve_all_for`'s blocking clause.
+        write!(f, "#b")?;
+        for i in (0..self.width).rev() {
+            let byte = self.bytes.get((i / 8) as usize).cloned().unwrap_or(0);
+            write!(f, "{}", (byte >> (i % 8)) & 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn hex_to_le_bytes(digits: &str) -> Vec<u8> {
+    // Hex digits come most-significant-first; pair them up from the tail so odd-length input
+    // (an implicit leading nibble of zero) is handled the same as even-length input.
+    let chars: Vec<char> = digits.chars().collect();
+    let mut bytes = Vec::with_capacity((chars.len() + 1) / 2);
+    let mut i = chars.len();
+    while i > 0 {
+        let lo = chars[i - 1].to_digit(16).unwrap_or(0) as u8;
+        let hi = if i >= 2 {
+            chars[i - 2].to_digit(16).unwrap_or(0) as u8
+        } else {
+            0
+        };
+        bytes.push((hi << 4) | lo);
+        i = if i >= 2 { i - 2 } else { 0 };
+    }
+    bytes
+}
+
+fn bin_to_le_bytes(digits: &str) -> Vec<u8> {
+    let bits: Vec<u32> = digits.chars().map(|c| c.to_digit(2).unwrap_or(0)).collect();
+    let mut bytes = vec![0u8; (bits.len() + 7) / 8];
+    for (i, bit) in bits.iter().rev().enumerate() {
+        if *bit != 0 {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+/// Convert an arbitrary-precision decimal string into little-endian base-256 bytes, since the
+/// value in `(_ bvN w)` can exceed `u64` for `w > 64`. Always padded with zero bytes up to
+/// `ceil(width / 8)`, per `BitVecValue`'s own invariant, even when the decimal magnitude alone
+/// would fit in fewer bytes (e.g. `(_ bv5 128)` is 16 bytes, not 1).
+fn decimal_to_le_bytes(decimal: &str, width: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![0];
+    for c in decimal.chars() {
+        let digit = c.to_digit(10).unwrap_or(0) as u32;
+        let mut carry = digit;
+        for byte in bytes.iter_mut() {
+            let acc = *byte as u32 * 10 + carry;
+            *byte = (acc & 0xff) as u8;
+            carry = acc >> 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    let byte_len = ((width + 7) / 8) as usize;
+    if bytes.len() < byte_len {
+        bytes.resize(byte_len, 0);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_literal() {
+        let value = BitVecValue::parse("#x05").unwrap();
+        assert_eq!(value.width(), 8);
+        assert_eq!(value.to_u64_lossy(), 5);
+    }
+
+    #[test]
+    fn parse_binary_literal() {
+        let value = BitVecValue::parse("#b1010").unwrap();
+        assert_eq!(value.width(), 4);
+        assert_eq!(value.to_u64_lossy(), 10);
+    }
+
+    #[test]
+    fn parse_decimal_literal_pads_to_declared_width() {
+        // A magnitude of 5 fits in a single byte, but the declared width is 128 bits; the byte
+        // vector must still be padded out to ceil(128 / 8), not truncated to the magnitude.
+        let value = BitVecValue::parse("(_ bv5 128)").unwrap();
+        assert_eq!(value.width(), 128);
+        assert_eq!(value.bytes().len(), 16);
+        assert_eq!(value.to_u64_lossy(), 5);
+    }
+
+    #[test]
+    fn parse_decimal_literal_exceeding_u64() {
+        // 2^64, well past what a u64 can hold, exercises the carry loop in decimal_to_le_bytes.
+        let value = BitVecValue::parse("(_ bv18446744073709551616 72)").unwrap();
+        assert_eq!(value.width(), 72);
+        assert_eq!(value.bytes(), &[0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_literal_forms() {
+        assert!(BitVecValue::parse("5").is_none());
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let value = BitVecValue::parse("#b00000101").unwrap();
+        assert_eq!(value.to_string(), "#b00000101");
+    }
+
+    #[test]
+    fn display_uses_exact_width_for_non_byte_aligned_value() {
+        // A 1-bit flag must print as a single `#b` digit, not rounded up to a whole byte.
+        let value = BitVecValue::new(1, vec![1]);
+        assert_eq!(value.to_string(), "#b1");
+    }
+}